@@ -0,0 +1,55 @@
+//! Measures how late the vigil watcher wakes up for its checks, both on an idle system and with
+//! every CPU kept busy.  Run with `cargo bench --bench jitter`.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const INTERVAL_MS: usize = 10;
+const RUN_TIME: Duration = Duration::from_secs(2);
+
+fn measure(label: &str) {
+    let samples = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let (vigil, watcher) = vigil::Builder::new(INTERVAL_MS)
+        .on_event({
+            let samples = samples.clone();
+            move |event| samples.lock().unwrap().push(event.jitter)
+        })
+        .build();
+    thread::sleep(RUN_TIME);
+    drop(vigil);
+    watcher.join().unwrap();
+
+    let mut samples = samples.lock().unwrap();
+    samples.sort();
+    let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+    println!(
+        "{:>6}: {} checks, p50 {:?}, p99 {:?}, max {:?}",
+        label,
+        samples.len(),
+        percentile(50),
+        percentile(99),
+        percentile(100)
+    );
+}
+
+fn main() {
+    measure("idle");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let load: Vec<_> = (0..thread::available_parallelism().map_or(1, |n| n.get()))
+        .map(|_| {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    std::hint::spin_loop();
+                }
+            })
+        })
+        .collect();
+    measure("loaded");
+    stop.store(true, Ordering::Relaxed);
+    for thread in load {
+        thread.join().unwrap();
+    }
+}
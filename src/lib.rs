@@ -9,10 +9,16 @@
 #[macro_use]
 extern crate log;
 
+use std::fmt;
 use std::sync::atomic;
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub mod logger;
+pub mod registry;
+pub mod thread;
+#[cfg(feature = "async")]
+pub mod time;
 
 const INIT: usize = 0;
 const LIVE: usize = 1;
@@ -20,38 +26,39 @@ const TEST: usize = 2;
 const RISK: usize = 3;
 const DEAD: usize = 4;
 
+/// Longest interval the watcher will sleep for, so that huge intervals can't overflow `Instant`.
+const MAX_INTERVAL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Value of `notified_at_us` before the first notification is seen.
+const NEVER: u64 = u64::MAX;
+
 /// Represents a single vigil over the code.  Should be notified every `tick_interval`, if enough
 /// intervals pass without a notification the callback will be fired (on a separate thread).
 pub struct Vigil {
     shared: Arc<VigilShared>,
+    registration: Option<registry::Registration>,
 }
 
 impl Vigil {
     /// Create a new vigil object.  The three callbacks are all optional.  Note that no callbacks
     /// will be fired until the first notification has occurred (this allows the vigil to be
-    /// created ahead of the worker thread without causing spurious logs/callbacks).
+    /// created ahead of the worker thread without causing spurious logs/callbacks).  Use
+    /// `Builder::arming` to change this.
+    ///
+    /// See `Builder` for further configuration options.
     pub fn create(
         interval_ms: usize,
         missed_test_cb: Option<Callback>,
         at_risk_cb: Option<Callback>,
         stall_detected_cb: Option<Callback>,
-    ) -> (Self, thread::JoinHandle<()>) {
-        let shared = Arc::new(VigilShared {
-            tick_interval: atomic::AtomicUsize::new(interval_ms),
-            state: atomic::AtomicUsize::new(INIT),
-            terminated: atomic::AtomicBool::new(false),
-        });
-        let callbacks = VigilCallbacks {
+    ) -> (Self, std::thread::JoinHandle<()>) {
+        Builder {
             missed_test_cb,
             at_risk_cb,
             stall_detected_cb,
-        };
-        let thread = thread::spawn({
-            let shared = shared.clone();
-            move || shared.watch(callbacks)
-        });
-
-        (Vigil { shared }, thread)
+            ..Builder::new(interval_ms)
+        }
+        .build()
     }
 
     /// Indicate to the vigil that the code is still active and alive.  This should be done in the
@@ -60,36 +67,664 @@ impl Vigil {
     /// unavailable to notify for an extended period of time, it should use `set_interval` rather
     /// than faking up notifications.
     pub fn notify(&self) {
-        self.shared.state.store(LIVE, atomic::Ordering::Relaxed);
+        self.shared
+            .heartbeat
+            .fetch_add(1, atomic::Ordering::Relaxed);
     }
 
     /// Change the interval between expected notifications.  Useful if a worker thread is expecting
     /// to block on a long operation (e.g. a blocking HTTP request, or a CPU intensive
     /// calculation).  This interval will be changed until `set_interval` is called again (so code
-    /// should shorten the interval once the long-blocking work is completed).
+    /// should shorten the interval once the long-blocking work is completed).  While any
+    /// `extend` guards are live, the interval used is the longest of this and theirs.
     pub fn set_interval(&self, interval_ms: usize) {
-        self.shared
-            .tick_interval
-            .store(interval_ms, atomic::Ordering::Relaxed);
+        let mut intervals = self.shared.intervals.lock().unwrap();
+        intervals.base_ms = interval_ms;
+        self.shared.apply_intervals(&intervals);
         self.notify();
     }
+
+    /// Extend the interval between expected notifications to at least `interval_ms` until the
+    /// returned guard is dropped.  This is a scoped form of `set_interval`, ensuring the interval
+    /// is shortened again even on early return.  Guards may overlap (e.g. from several tasks
+    /// sharing a vigil), in which case the longest live extension applies.
+    ///
+    /// `phase` describes what the watched code is doing meanwhile (e.g. "waiting for db lock"),
+    /// and is included in logs and events until the guard is dropped.
+    pub fn extend(&self, interval_ms: usize, phase: &'static str) -> IntervalGuard<'_> {
+        let mut intervals = self.shared.intervals.lock().unwrap();
+        let id = intervals.next_id;
+        intervals.next_id += 1;
+        intervals.extensions.push(Extension {
+            id,
+            interval_ms,
+            phase,
+        });
+        self.shared.apply_intervals(&intervals);
+        self.notify();
+        IntervalGuard { vigil: self, id }
+    }
+
+    /// The stage the vigil is currently in: `Live` if notified since the last check, otherwise the
+    /// stage reported by the last check.  This only reads a few atomics, so is cheap enough to
+    /// call from health endpoints on every request, even across large numbers of vigils.
+    pub fn stage(&self) -> Stage {
+        self.shared.stage()
+    }
+
+    /// How long ago the vigil was last notified, or `None` if it never has been.  Notifications
+    /// are timestamped by the watcher when it next checks, so this is only accurate to within one
+    /// interval (and is zero if the vigil has been notified since the last check).
+    pub fn since_notified(&self) -> Option<Duration> {
+        self.shared.since_notified()
+    }
+
+    /// How late the watcher has been waking up for its checks, compared to when they were
+    /// scheduled.  Stalls are detected up to this much later than the configured interval
+    /// implies, so this can be used to validate detection latency on a given system.
+    pub fn jitter(&self) -> Jitter {
+        Jitter {
+            last: Duration::from_micros(self.shared.last_jitter_us.load(atomic::Ordering::Relaxed)),
+            max: Duration::from_micros(self.shared.max_jitter_us.load(atomic::Ordering::Relaxed)),
+        }
+    }
+
+    /// Bind the vigil to the calling thread, so that the thread's name and OS thread ID are
+    /// included in the vigil's logs and events.  This is done automatically by
+    /// `vigil::thread::register` and threads spawned by `vigil::thread::Builder`.
+    pub fn bind_current_thread(&self) {
+        *self.shared.thread.lock().unwrap() = Some(Arc::new(ThreadInfo::current()));
+    }
+
+    /// The thread the vigil is bound to, if any.
+    pub fn thread(&self) -> Option<Arc<ThreadInfo>> {
+        self.shared.thread.lock().unwrap().clone()
+    }
+
+    /// The name given to the vigil by `Builder::name`, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.shared.name.as_deref()
+    }
 }
 
 impl Drop for Vigil {
     fn drop(&mut self) {
+        if let Some(ref registration) = self.registration {
+            registration.unregister();
+        }
         self.shared
             .terminated
             .store(true, atomic::Ordering::Relaxed);
     }
 }
 
+/// Observed watcher wakeup jitter.  See `Vigil::jitter`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Jitter {
+    /// The jitter of the most recent check.
+    pub last: Duration,
+    /// The largest jitter seen over the lifetime of the vigil.
+    pub max: Duration,
+}
+
+/// Removes an extension of a vigil's interval when dropped.  See `Vigil::extend`.
+pub struct IntervalGuard<'a> {
+    vigil: &'a Vigil,
+    id: u64,
+}
+
+impl Drop for IntervalGuard<'_> {
+    fn drop(&mut self) {
+        let shared = &self.vigil.shared;
+        let mut intervals = shared.intervals.lock().unwrap();
+        intervals
+            .extensions
+            .retain(|extension| extension.id != self.id);
+        shared.apply_intervals(&intervals);
+        self.vigil.notify();
+    }
+}
+
+/// A late-bound handle to a vigil.  Starts out as a no-op and can later be bound to a real
+/// `Vigil`, allowing libraries to notify a vigil that the application creates after the fact.
+/// As `Notifier::noop` is `const`, a notifier can be held in a `static`:
+///
+/// ```
+/// static NOTIFIER: vigil::Notifier = vigil::Notifier::noop();
+///
+/// fn do_work() {
+///     NOTIFIER.notify();
+/// }
+///
+/// do_work();
+/// let (vigil, _thread) = vigil::Vigil::create(1000, None, None, None);
+/// assert!(NOTIFIER.bind(vigil).is_ok());
+/// do_work();
+/// ```
+#[derive(Default)]
+pub struct Notifier {
+    vigil: OnceLock<Vigil>,
+}
+
+impl Notifier {
+    /// Create a notifier which does nothing until it is bound.
+    pub const fn noop() -> Self {
+        Notifier {
+            vigil: OnceLock::new(),
+        }
+    }
+
+    /// Bind the notifier to `vigil`.  A notifier can only be bound once, if it is already bound
+    /// then `vigil` is handed back as the error.
+    pub fn bind(&self, vigil: Vigil) -> Result<(), Vigil> {
+        self.vigil.set(vigil)
+    }
+
+    /// The vigil this notifier is bound to, if any.
+    pub fn vigil(&self) -> Option<&Vigil> {
+        self.vigil.get()
+    }
+
+    /// As `Vigil::notify`, or does nothing if the notifier is unbound.
+    pub fn notify(&self) {
+        if let Some(vigil) = self.vigil.get() {
+            vigil.notify();
+        }
+    }
+
+    /// As `Vigil::set_interval`, or does nothing if the notifier is unbound.
+    pub fn set_interval(&self, interval_ms: usize) {
+        if let Some(vigil) = self.vigil.get() {
+            vigil.set_interval(interval_ms);
+        }
+    }
+}
+
+/// Builder for a `Vigil`, allowing the optional parts of the vigil to be configured before the
+/// watcher thread is started.
+pub struct Builder {
+    interval_ms: usize,
+    name: Option<String>,
+    missed_test_cb: Option<Callback>,
+    at_risk_cb: Option<Callback>,
+    stall_detected_cb: Option<Callback>,
+    event_cb: Option<EventCallback>,
+    severities: Severities,
+    arming: Arming,
+    snapshot: Option<Snapshot>,
+    registry: Option<registry::Registry>,
+    labels: Vec<(String, String)>,
+}
+
+impl Builder {
+    /// Start building a vigil that expects a notification every `interval_ms`.
+    pub fn new(interval_ms: usize) -> Self {
+        Builder {
+            interval_ms,
+            name: None,
+            missed_test_cb: None,
+            at_risk_cb: None,
+            stall_detected_cb: None,
+            event_cb: None,
+            severities: Severities::default(),
+            arming: Arming::default(),
+            snapshot: None,
+            registry: None,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Name the vigil.  The name is included in logs and used to name the watcher thread.
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Callback fired when the watched code misses a single check.
+    pub fn missed_test<F: Fn() + Send + 'static>(mut self, cb: F) -> Self {
+        self.missed_test_cb = Some(Box::new(cb));
+        self
+    }
+
+    /// Callback fired when the watched code misses multiple checks.
+    pub fn at_risk<F: Fn() + Send + 'static>(mut self, cb: F) -> Self {
+        self.at_risk_cb = Some(Box::new(cb));
+        self
+    }
+
+    /// Callback fired on every check once the watched code is considered stalled.
+    pub fn stall_detected<F: Fn() + Send + 'static>(mut self, cb: F) -> Self {
+        self.stall_detected_cb = Some(Box::new(cb));
+        self
+    }
+
+    /// Sink called with an `Event` on every check made by the watcher.  This is intended for
+    /// forwarding vigil state to external systems (metrics, alerting webhooks, journald etc.)
+    /// which can use the event's `Severity` directly rather than translating stages themselves.
+    pub fn on_event<F: Fn(&Event) + Send + 'static>(mut self, cb: F) -> Self {
+        self.event_cb = Some(Box::new(cb));
+        self
+    }
+
+    /// Override the mapping from vigil stages to severities.
+    pub fn severities(mut self, severities: Severities) -> Self {
+        self.severities = severities;
+        self
+    }
+
+    /// Choose when the vigil starts checking for notifications.
+    pub fn arming(mut self, arming: Arming) -> Self {
+        self.arming = arming;
+        self
+    }
+
+    /// Function describing the watched code's internal state (queue depths, current work item,
+    /// retry counts etc.).  When a stall is detected this is called and its result included in
+    /// the logs and events.  The function is run on a dedicated snapshot thread (started the
+    /// first time it is needed) and abandoned if it takes longer than `timeout`, so may safely
+    /// take locks the stalled code might be holding; it will not be called again until a
+    /// previous, abandoned call has finished.
+    pub fn snapshot<F: Fn() -> String + Send + 'static>(mut self, timeout: Duration, f: F) -> Self {
+        self.snapshot = Some(Snapshot {
+            timeout,
+            running: Arc::new(atomic::AtomicBool::new(false)),
+            worker: SnapshotWorker::Idle(Box::new(f)),
+        });
+        self
+    }
+
+    /// Add the vigil to `registry` for as long as it exists, so it can be found by
+    /// `Registry::query`.
+    pub fn registry(mut self, registry: &registry::Registry) -> Self {
+        self.registry = Some(registry.clone());
+        self
+    }
+
+    /// Label the vigil, for filtering registry queries.
+    pub fn label(mut self, key: String, value: String) -> Self {
+        self.labels.push((key, value));
+        self
+    }
+
+    /// Create the vigil and start its watcher thread.
+    pub fn build(self) -> (Vigil, std::thread::JoinHandle<()>) {
+        self.try_build()
+            .expect("failed to spawn vigil watcher thread")
+    }
+
+    /// As `build`, but returns an error if the watcher thread can't be spawned.
+    pub(crate) fn try_build(self) -> std::io::Result<(Vigil, std::thread::JoinHandle<()>)> {
+        let watcher = match self.name {
+            Some(ref name) => std::thread::Builder::new().name(format!("vigil-{}", name)),
+            None => std::thread::Builder::new(),
+        };
+        let shared = Arc::new(VigilShared {
+            name: self.name,
+            thread: Mutex::new(None),
+            intervals: Mutex::new(Intervals {
+                base_ms: self.interval_ms,
+                extensions: Vec::new(),
+                next_id: 0,
+            }),
+            tick_interval: atomic::AtomicUsize::new(self.interval_ms),
+            heartbeat: CachePadded(atomic::AtomicU64::new(0)),
+            seen_heartbeat: atomic::AtomicU64::new(0),
+            state: atomic::AtomicUsize::new(INIT),
+            created: Instant::now(),
+            notified_at_us: atomic::AtomicU64::new(NEVER),
+            terminated: atomic::AtomicBool::new(false),
+            last_jitter_us: atomic::AtomicU64::new(0),
+            max_jitter_us: atomic::AtomicU64::new(0),
+        });
+        let callbacks = VigilCallbacks {
+            missed_test_cb: self.missed_test_cb,
+            at_risk_cb: self.at_risk_cb,
+            stall_detected_cb: self.stall_detected_cb,
+            event_cb: self.event_cb,
+            severities: self.severities,
+            snapshot: self.snapshot,
+        };
+        let stage = match self.arming {
+            Arming::ArmedImmediately => Stage::Live,
+            _ => Stage::Init,
+        };
+        let arm_at = match self.arming {
+            Arming::ArmedAfter(delay) => Some(Instant::now() + delay),
+            _ => None,
+        };
+        let thread = watcher.spawn({
+            let shared = shared.clone();
+            move || shared.watch(callbacks, stage, arm_at)
+        })?;
+        let registration = self
+            .registry
+            .map(|registry| registry.register(shared.clone(), self.labels));
+
+        Ok((
+            Vigil {
+                shared,
+                registration,
+            },
+            thread,
+        ))
+    }
+}
+
+/// When a vigil starts checking for notifications.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arming {
+    /// No checks are made until the first notification.  This allows the vigil to be created
+    /// ahead of the worker without causing spurious logs/callbacks, but a worker which hangs
+    /// before notifying for the first time will never be detected.
+    #[default]
+    WaitForFirstNotify,
+    /// Checks start as soon as the vigil is created, as if it had just been notified.
+    ArmedImmediately,
+    /// Checks start on the first notification, or after the given delay if that is sooner.
+    ArmedAfter(Duration),
+}
+
+/// The stages a vigil moves through as notifications are missed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// No notification has been received yet.
+    Init,
+    /// The watched code notified since the last check.
+    Live,
+    /// The watched code missed a single check.
+    MissedTest,
+    /// The watched code missed multiple checks.
+    AtRisk,
+    /// The watched code is still unresponsive and is likely stalled.
+    Stalled,
+}
+
+impl Stage {
+    fn index(self) -> usize {
+        match self {
+            Stage::Init => INIT,
+            Stage::Live => LIVE,
+            Stage::MissedTest => TEST,
+            Stage::AtRisk => RISK,
+            Stage::Stalled => DEAD,
+        }
+    }
+}
+
+/// How serious a vigil event is, in terms common to most alerting systems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational only, logged at `info` (syslog priority 6).
+    Info,
+    /// Worth investigating if it persists, logged at `warn` (syslog priority 4).
+    Warn,
+    /// Needs attention, logged at `error` (syslog priority 2).
+    Critical,
+    /// The watched code is considered dead, logged at `error` (syslog priority 0).
+    Fatal,
+}
+
+impl Severity {
+    /// The level at which events of this severity are logged.
+    pub fn log_level(self) -> log::Level {
+        match self {
+            Severity::Info => log::Level::Info,
+            Severity::Warn => log::Level::Warn,
+            Severity::Critical | Severity::Fatal => log::Level::Error,
+        }
+    }
+
+    /// The syslog priority (as used by journald's `PRIORITY` field) for this severity.
+    pub fn syslog_priority(self) -> u8 {
+        match self {
+            Severity::Info => 6,
+            Severity::Warn => 4,
+            Severity::Critical => 2,
+            Severity::Fatal => 0,
+        }
+    }
+
+    /// A short lowercase name for this severity, suitable for metric labels.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Critical => "critical",
+            Severity::Fatal => "fatal",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Mapping from each `Stage` to the `Severity` it is reported with.
+///
+/// By default, `Init` and `Live` are `Info`, `MissedTest` is `Warn`, `AtRisk` is `Critical` and
+/// `Stalled` is `Fatal`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Severities([Severity; 5]);
+
+impl Severities {
+    /// Report `stage` with the given `severity`.
+    pub fn with(mut self, stage: Stage, severity: Severity) -> Self {
+        self.0[stage.index()] = severity;
+        self
+    }
+
+    /// The severity `stage` is reported with.
+    pub fn get(&self, stage: Stage) -> Severity {
+        self.0[stage.index()]
+    }
+}
+
+impl Default for Severities {
+    fn default() -> Self {
+        Severities([
+            Severity::Info,
+            Severity::Info,
+            Severity::Warn,
+            Severity::Critical,
+            Severity::Fatal,
+        ])
+    }
+}
+
+/// Identifies the thread a vigil is bound to (see `Vigil::bind_current_thread`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreadInfo {
+    /// The thread's name, if it has one.
+    pub name: Option<String>,
+    /// The thread ID assigned by the OS (as shown by `top -H`, gdb etc.), on platforms where
+    /// this is available.
+    pub os_id: Option<u64>,
+}
+
+impl ThreadInfo {
+    /// Describe the calling thread.
+    pub fn current() -> Self {
+        ThreadInfo {
+            name: std::thread::current().name().map(str::to_string),
+            os_id: os_thread_id(),
+        }
+    }
+}
+
+impl fmt::Display for ThreadInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("thread")?;
+        if let Some(ref name) = self.name {
+            write!(f, " '{}'", name)?;
+        }
+        if let Some(os_id) = self.os_id {
+            write!(f, " tid {}", os_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn os_thread_id() -> Option<u64> {
+    // SAFETY: `SYS_gettid` takes no arguments, has no side effects and cannot fail.  It is called
+    // via `syscall` rather than `libc::gettid` as the latter needs glibc 2.30 or later.
+    Some(unsafe { libc::syscall(libc::SYS_gettid) } as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_thread_id() -> Option<u64> {
+    None
+}
+
+/// A single check made by the watcher, as passed to the `Builder::on_event` sink.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Event {
+    /// The stage the vigil was in at this check.
+    pub stage: Stage,
+    /// The severity `stage` maps to (see `Builder::severities`).
+    pub severity: Severity,
+    /// The thread the vigil is bound to, if any.
+    pub thread: Option<Arc<ThreadInfo>>,
+    /// The phases of any live `Vigil::extend` guards (including those held by the `time`
+    /// helpers), in the order they were started.
+    pub phases: Vec<&'static str>,
+    /// How late the watcher woke up for this check (see `Vigil::jitter`).
+    pub jitter: Duration,
+    /// The result of the `Builder::snapshot` function, if one was taken for this check.
+    pub snapshot: Option<String>,
+}
+
 type Callback = Box<dyn Fn() + Send + 'static>;
+type EventCallback = Box<dyn Fn(&Event) + Send + 'static>;
+type SnapshotFn = Box<dyn Fn() -> String + Send + 'static>;
+type SnapshotResult = Result<String, &'static str>;
+
+/// A user-supplied snapshot function, see `Builder::snapshot`.
+struct Snapshot {
+    timeout: Duration,
+    running: Arc<atomic::AtomicBool>,
+    worker: SnapshotWorker,
+}
+
+/// The thread which runs a snapshot function, each request carrying a channel for the result.
+enum SnapshotWorker {
+    /// Not needed yet.
+    Idle(SnapshotFn),
+    Started(std::sync::mpsc::Sender<std::sync::mpsc::Sender<SnapshotResult>>),
+    /// The thread couldn't be spawned, so no snapshots can be taken.
+    Failed,
+}
+
+impl Snapshot {
+    /// Take a snapshot, or return why one couldn't be taken.
+    fn take(&mut self) -> SnapshotResult {
+        if self.running.swap(true, atomic::Ordering::Relaxed) {
+            return Err("skipped, a previous snapshot is still running");
+        }
+        if let SnapshotWorker::Idle(_) = self.worker {
+            self.start();
+        }
+        let requests = match self.worker {
+            SnapshotWorker::Started(ref requests) => requests,
+            _ => {
+                self.running.store(false, atomic::Ordering::Relaxed);
+                return Err("failed, couldn't spawn a thread to take it");
+            }
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        if requests.send(tx).is_err() {
+            self.running.store(false, atomic::Ordering::Relaxed);
+            return Err("failed, the snapshot thread has exited");
+        }
+        match rx.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                Err("timed out, the snapshot function may be hung")
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                Err("failed, the snapshot thread has exited")
+            }
+        }
+    }
+
+    /// Spawn the snapshot thread.  It exits once the watcher (and so this `Snapshot`) is gone.
+    fn start(&mut self) {
+        let f = match std::mem::replace(&mut self.worker, SnapshotWorker::Failed) {
+            SnapshotWorker::Idle(f) => f,
+            worker => {
+                self.worker = worker;
+                return;
+            }
+        };
+        let (requests, rx) = std::sync::mpsc::channel::<std::sync::mpsc::Sender<_>>();
+        let running = self.running.clone();
+        let spawned = std::thread::Builder::new()
+            .name("vigil-snapshot".to_string())
+            .spawn(move || {
+                for reply in rx {
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(&f))
+                        .map_err(|_| "failed, the snapshot function panicked");
+                    running.store(false, atomic::Ordering::Relaxed);
+                    let _ = reply.send(result);
+                }
+            });
+        if spawned.is_ok() {
+            self.worker = SnapshotWorker::Started(requests);
+        }
+    }
+}
 
 /// The shared state of a vigil.  This is shared between all vigil handles and the watcher thread.
+///
+/// `heartbeat` is only written by notifiers (and sits on its own cache line), while
+/// `seen_heartbeat`, `state` and the jitter statistics are only written by the watcher.
 struct VigilShared {
+    name: Option<String>,
+    thread: Mutex<Option<Arc<ThreadInfo>>>,
+    intervals: Mutex<Intervals>,
+    /// The effective interval derived from `intervals`, read by the watcher.
     tick_interval: atomic::AtomicUsize,
+    heartbeat: CachePadded<atomic::AtomicU64>,
+    seen_heartbeat: atomic::AtomicU64,
     state: atomic::AtomicUsize,
+    created: Instant,
+    notified_at_us: atomic::AtomicU64,
     terminated: atomic::AtomicBool,
+    last_jitter_us: atomic::AtomicU64,
+    max_jitter_us: atomic::AtomicU64,
+}
+
+/// Aligns (and so pads) its contents to a cache line, so that notifiers writing `heartbeat` don't
+/// contend with the watcher writing neighbouring fields.  128 bytes covers the adjacent-line
+/// prefetching on modern x86_64 and the larger lines on some aarch64 parts.
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+impl<T> std::ops::Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The intervals requested by `Vigil::set_interval` and any live `Vigil::extend` guards.
+struct Intervals {
+    base_ms: usize,
+    /// Live extensions, in the order they were made.
+    extensions: Vec<Extension>,
+    next_id: u64,
+}
+
+/// A single `Vigil::extend` guard's extension.
+struct Extension {
+    id: u64,
+    interval_ms: usize,
+    phase: &'static str,
 }
 
 /// The callbacks associated with the Vigil
@@ -97,62 +732,242 @@ struct VigilCallbacks {
     missed_test_cb: Option<Callback>,
     at_risk_cb: Option<Callback>,
     stall_detected_cb: Option<Callback>,
+    event_cb: Option<EventCallback>,
+    severities: Severities,
+    snapshot: Option<Snapshot>,
+}
+
+impl VigilCallbacks {
+    /// Report the current stage to the event sink, returning the severity it was reported at.
+    fn report(
+        &self,
+        stage: Stage,
+        thread: &Option<Arc<ThreadInfo>>,
+        phases: &[&'static str],
+        jitter: Duration,
+        snapshot: &Option<String>,
+    ) -> Severity {
+        let severity = self.severities.get(stage);
+        if let Some(ref cb) = self.event_cb {
+            cb(&Event {
+                stage,
+                severity,
+                thread: thread.clone(),
+                phases: phases.to_vec(),
+                jitter,
+                snapshot: snapshot.clone(),
+            });
+        }
+        severity
+    }
 }
 
 impl VigilShared {
-    fn watch(&self, callbacks: VigilCallbacks) {
+    fn apply_intervals(&self, intervals: &Intervals) {
+        let interval_ms = intervals
+            .extensions
+            .iter()
+            .fold(intervals.base_ms, |max, extension| {
+                max.max(extension.interval_ms)
+            });
+        self.tick_interval
+            .store(interval_ms, atomic::Ordering::Relaxed);
+    }
+
+    /// The phases of all live `Vigil::extend` guards.
+    fn phases(&self) -> Vec<&'static str> {
+        let intervals = self.intervals.lock().unwrap();
+        intervals
+            .extensions
+            .iter()
+            .map(|extension| extension.phase)
+            .collect()
+    }
+
+    fn stage(&self) -> Stage {
+        // Pairs with the watcher's `Release` store, so `state` and `notified_at_us` are at least as
+        // new as `seen`.
+        let seen = self.seen_heartbeat.load(atomic::Ordering::Acquire);
+        if self.heartbeat.load(atomic::Ordering::Relaxed) != seen {
+            return Stage::Live;
+        }
+        match self.state.load(atomic::Ordering::Relaxed) {
+            LIVE => Stage::Live,
+            TEST => Stage::MissedTest,
+            RISK => Stage::AtRisk,
+            DEAD => Stage::Stalled,
+            _ => Stage::Init,
+        }
+    }
+
+    fn since_notified(&self) -> Option<Duration> {
+        // Pairs with the watcher's `Release` store, so `state` and `notified_at_us` are at least as
+        // new as `seen`.
+        let seen = self.seen_heartbeat.load(atomic::Ordering::Acquire);
+        if self.heartbeat.load(atomic::Ordering::Relaxed) != seen {
+            return Some(Duration::ZERO);
+        }
+        match self.notified_at_us.load(atomic::Ordering::Relaxed) {
+            NEVER => None,
+            at_us => Some(
+                self.created
+                    .elapsed()
+                    .saturating_sub(Duration::from_micros(at_us)),
+            ),
+        }
+    }
+
+    /// Run the watcher.  Notifiers only ever increment `heartbeat` and the watcher only ever reads
+    /// it, tracking the stage locally and publishing it to `state` for `Vigil::stage`.  `stage` is
+    /// the stage that will be reported at the next check, unless a heartbeat arrives first.
+    fn watch(&self, mut callbacks: VigilCallbacks, mut stage: Stage, mut arm_at: Option<Instant>) {
+        let mut last_heartbeat = 0;
+        // Checks are scheduled against absolute deadlines, so that time spent making the check
+        // (and running callbacks) doesn't accumulate as drift.
+        let mut next_check = Instant::now();
+        let mut overrun = Duration::ZERO;
         loop {
+            let jitter = overrun + Instant::now().saturating_duration_since(next_check);
+            overrun = Duration::ZERO;
+
+            let thread = self.thread.lock().unwrap().clone();
+            let phases = self.phases();
+            let mut prefix = match (&self.name, &thread) {
+                (Some(name), Some(thread)) => format!("{} ({})", name, thread),
+                (Some(name), None) => name.clone(),
+                (None, Some(thread)) => thread.to_string(),
+                (None, None) => String::new(),
+            };
+            if !phases.is_empty() {
+                if !prefix.is_empty() {
+                    prefix.push(' ');
+                }
+                prefix.push_str(&format!("[{}]", phases.join(", ")));
+            }
+            if !prefix.is_empty() {
+                prefix.push_str(": ");
+            }
+
             if self.terminated.load(atomic::Ordering::Relaxed) {
-                info!("Vigil is terminating");
+                info!("{}Vigil is terminating", prefix);
                 break;
             }
+            self.record_jitter(jitter);
+
+            let heartbeat = self.heartbeat.load(atomic::Ordering::Relaxed);
+            if heartbeat != last_heartbeat {
+                last_heartbeat = heartbeat;
+                stage = Stage::Live;
+                self.notified_at_us.store(
+                    self.created.elapsed().as_micros() as u64,
+                    atomic::Ordering::Relaxed,
+                );
+            }
+            self.state.store(stage.index(), atomic::Ordering::Relaxed);
+            // Published last (with `Release`) so readers that see it also see the `state` and
+            // `notified_at_us` written for it.
+            self.seen_heartbeat
+                .store(last_heartbeat, atomic::Ordering::Release);
 
-            match self.state.load(atomic::Ordering::Relaxed) {
-                INIT => info!("Liveness not initialized... waiting"),
-                LIVE => {
-                    info!("Software is live - Re-testing");
-                    self.state.store(TEST, atomic::Ordering::Relaxed);
+            let snapshot = match (stage, &mut callbacks.snapshot) {
+                (Stage::AtRisk | Stage::Stalled, Some(snapshot)) => match snapshot.take() {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(reason) => {
+                        warn!("{}Worker state snapshot {}", prefix, reason);
+                        None
+                    }
+                },
+                _ => None,
+            };
+            let severity = callbacks.report(stage, &thread, &phases, jitter, &snapshot);
+            match stage {
+                Stage::Init => {
+                    log!(
+                        severity.log_level(),
+                        "{}Liveness not initialized... waiting ({})",
+                        prefix,
+                        severity
+                    );
+                    if arm_at.is_some_and(|arm_at| Instant::now() >= arm_at) {
+                        info!("{}Arming delay expired - Testing", prefix);
+                        arm_at = None;
+                        stage = Stage::MissedTest;
+                    }
+                }
+                Stage::Live => {
+                    log!(
+                        severity.log_level(),
+                        "{}Software is live - Re-testing ({})",
+                        prefix,
+                        severity
+                    );
+                    stage = Stage::MissedTest;
                 }
-                TEST => {
-                    warn!("Software missed a test - Temporary glitch/slowdown?");
-                    let _ = self.state.compare_exchange(
-                        TEST,
-                        RISK,
-                        atomic::Ordering::Relaxed,
-                        atomic::Ordering::Relaxed,
+                Stage::MissedTest => {
+                    log!(
+                        severity.log_level(),
+                        "{}Software missed a test - Temporary glitch/slowdown? ({})",
+                        prefix,
+                        severity
                     );
+                    stage = Stage::AtRisk;
                     if let Some(ref cb) = callbacks.missed_test_cb {
                         cb();
                     }
                 }
-                RISK => {
-                    error!("Software missed multiple tests - Stall detected?");
-                    let _ = self.state.compare_exchange(
-                        RISK,
-                        DEAD,
-                        atomic::Ordering::Relaxed,
-                        atomic::Ordering::Relaxed,
+                Stage::AtRisk => {
+                    log!(
+                        severity.log_level(),
+                        "{}Software missed multiple tests - Stall detected? ({})",
+                        prefix,
+                        severity
                     );
+                    if let Some(ref snapshot) = snapshot {
+                        log!(severity.log_level(), "{}Worker state: {}", prefix, snapshot);
+                    }
+                    stage = Stage::Stalled;
                     if let Some(ref cb) = callbacks.at_risk_cb {
                         cb();
                     }
                 }
-                DEAD => {
-                    error!("Software is still unresponsive - Likely stalled");
+                Stage::Stalled => {
+                    log!(
+                        severity.log_level(),
+                        "{}Software is still unresponsive - Likely stalled ({})",
+                        prefix,
+                        severity
+                    );
+                    if let Some(ref snapshot) = snapshot {
+                        log!(severity.log_level(), "{}Worker state: {}", prefix, snapshot);
+                    }
                     if let Some(ref cb) = callbacks.stall_detected_cb {
                         cb();
                     }
                 }
-                v => {
-                    warn!("Liveness check had unexpected value {}, resetting", v);
-                    self.state.store(INIT, atomic::Ordering::Relaxed);
-                }
             }
 
             let interval_ms = self.tick_interval.load(atomic::Ordering::Relaxed) as u64;
-            thread::sleep(Duration::from_millis(interval_ms));
+            next_check += Duration::from_millis(interval_ms).min(MAX_INTERVAL);
+            let now = Instant::now();
+            if next_check > now {
+                std::thread::sleep(next_check - now);
+            } else {
+                // We've overrun the check entirely (e.g. a slow callback), so start afresh rather
+                // than firing a burst of checks to catch up.  The overrun still counts towards the
+                // next check's jitter, since that check is late by this much.
+                overrun = now - next_check;
+                next_check = now;
+            }
         }
     }
+
+    fn record_jitter(&self, jitter: Duration) {
+        let jitter_us = jitter.as_micros() as u64;
+        self.last_jitter_us
+            .store(jitter_us, atomic::Ordering::Relaxed);
+        self.max_jitter_us
+            .fetch_max(jitter_us, atomic::Ordering::Relaxed);
+    }
 }
 
 #[cfg(test)]
@@ -169,10 +984,7 @@ mod tests {
                 let status = status.clone();
                 move || status.store(RISK, atomic::Ordering::Relaxed)
             }),
-            Box::new({
-                let status = status;
-                move || status.store(DEAD, atomic::Ordering::Relaxed)
-            }),
+            Box::new(move || status.store(DEAD, atomic::Ordering::Relaxed)),
         )
     }
 
@@ -210,4 +1022,316 @@ mod tests {
     test!(miss_multiple_tests, 300, RISK);
     test!(complete_stall, 500, DEAD);
     test!(predicted_stall, 500, 750, INIT);
+
+    fn arming_test(arming: Arming, sleep_ms: u64, stage: Stage) {
+        let (vigil, thread) = Builder::new(100).arming(arming).build();
+        std::thread::sleep(Duration::from_millis(sleep_ms));
+        assert_eq!(stage, vigil.stage());
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn wait_for_first_notify_never_arms() {
+        arming_test(Arming::WaitForFirstNotify, 500, Stage::Init);
+    }
+
+    #[test]
+    fn armed_immediately() {
+        arming_test(Arming::ArmedImmediately, 450, Stage::Stalled);
+    }
+
+    #[test]
+    fn armed_after_delay() {
+        arming_test(
+            Arming::ArmedAfter(Duration::from_millis(350)),
+            250,
+            Stage::Init,
+        );
+        arming_test(
+            Arming::ArmedAfter(Duration::from_millis(350)),
+            900,
+            Stage::Stalled,
+        );
+    }
+
+    #[test]
+    fn stage_snapshot() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        assert_eq!(Stage::Init, vigil.stage());
+        vigil.notify();
+        assert_eq!(Stage::Live, vigil.stage());
+        std::thread::sleep(Duration::from_millis(450));
+        assert_eq!(Stage::Stalled, vigil.stage());
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn extend_restores_interval() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        {
+            let _guard = vigil.extend(750, "long operation");
+            assert_eq!(
+                750,
+                vigil.shared.tick_interval.load(atomic::Ordering::Relaxed)
+            );
+        }
+        assert_eq!(
+            100,
+            vigil.shared.tick_interval.load(atomic::Ordering::Relaxed)
+        );
+        assert_eq!(Stage::Live, vigil.stage());
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn many_notifiers() {
+        let status = Arc::new(atomic::AtomicUsize::new(INIT));
+        let (a, b, c) = create_callbacks(status.clone());
+        let (vigil, thread) = Vigil::create(100, Some(a), Some(b), Some(c));
+        let vigil = Arc::new(vigil);
+        let notifiers: Vec<_> = (0..4)
+            .map(|_| {
+                let vigil = vigil.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        vigil.notify();
+                        std::thread::sleep(Duration::from_millis(20));
+                    }
+                })
+            })
+            .collect();
+        for notifier in notifiers {
+            notifier.join().unwrap();
+        }
+        assert_eq!(INIT, status.load(atomic::Ordering::Relaxed));
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn since_notified() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        assert_eq!(None, vigil.since_notified());
+        vigil.notify();
+        assert_eq!(Some(Duration::ZERO), vigil.since_notified());
+        std::thread::sleep(Duration::from_millis(350));
+        let since = vigil.since_notified().unwrap();
+        assert!(since >= Duration::from_millis(200));
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn overlapping_extensions() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        let interval = || vigil.shared.tick_interval.load(atomic::Ordering::Relaxed);
+
+        let a = vigil.extend(500, "a");
+        let b = vigil.extend(1000, "b");
+        assert_eq!(1000, interval());
+        drop(a);
+        assert_eq!(1000, interval());
+        drop(b);
+        assert_eq!(100, interval());
+
+        let a = vigil.extend(500, "a");
+        let b = vigil.extend(1000, "b");
+        drop(b);
+        assert_eq!(500, interval());
+        vigil.set_interval(200);
+        assert_eq!(500, interval());
+        drop(a);
+        assert_eq!(200, interval());
+
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn late_bound_notifier() {
+        let notifier = Notifier::noop();
+        notifier.notify();
+        assert!(notifier.vigil().is_none());
+
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        assert!(notifier.bind(vigil).is_ok());
+        notifier.notify();
+        assert_eq!(Some(Stage::Live), notifier.vigil().map(Vigil::stage));
+
+        let (other, other_thread) = Vigil::create(100, None, None, None);
+        drop(notifier.bind(other).unwrap_err());
+        other_thread.join().unwrap();
+
+        drop(notifier);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn events_carry_thread() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (vigil, thread) = Builder::new(100)
+            .on_event(move |event| {
+                let _ = tx.send(event.thread.clone());
+            })
+            .build();
+        assert_eq!(None, rx.recv().unwrap());
+
+        let worker = std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(move || {
+                vigil.bind_current_thread();
+                vigil
+            })
+            .unwrap();
+        let vigil = worker.join().unwrap();
+        let info = vigil.thread().unwrap();
+        assert_eq!(Some("worker"), info.name.as_deref());
+        if cfg!(target_os = "linux") {
+            assert!(info.os_id.is_some());
+        }
+        std::thread::sleep(Duration::from_millis(150));
+        drop(vigil);
+        thread.join().unwrap();
+        assert!(rx.try_iter().any(|thread| thread == Some(info.clone())));
+    }
+
+    #[test]
+    fn jitter_is_measured() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (vigil, thread) = Builder::new(20)
+            .on_event(move |event| {
+                let _ = tx.send(event.jitter);
+            })
+            .build();
+        std::thread::sleep(Duration::from_millis(200));
+        let shared = vigil.shared.clone();
+        drop(vigil);
+        thread.join().unwrap();
+
+        let jitters: Vec<_> = rx
+            .try_iter()
+            .map(|jitter| jitter.as_micros() as u64)
+            .collect();
+        assert!(jitters.len() >= 5);
+        assert_eq!(
+            *jitters.last().unwrap(),
+            shared.last_jitter_us.load(atomic::Ordering::Relaxed)
+        );
+        assert_eq!(
+            *jitters.iter().max().unwrap(),
+            shared.max_jitter_us.load(atomic::Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    fn overrun_counts_as_jitter() {
+        let (vigil, thread) = Builder::new(20)
+            .on_event(|_| std::thread::sleep(Duration::from_millis(100)))
+            .build();
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(vigil.jitter().max >= Duration::from_millis(50));
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn stall_events_carry_snapshot() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (threads_tx, threads_rx) = std::sync::mpsc::channel();
+        let (vigil, thread) = Builder::new(100)
+            .snapshot(Duration::from_millis(50), move || {
+                let _ = threads_tx.send(std::thread::current().id());
+                "queue depth 3".to_string()
+            })
+            .on_event(move |event| {
+                let _ = tx.send((event.stage, event.snapshot.clone()));
+            })
+            .build();
+        vigil.notify();
+        std::thread::sleep(Duration::from_millis(450));
+        drop(vigil);
+        thread.join().unwrap();
+
+        let events: Vec<_> = rx.try_iter().collect();
+        let snapshot = Some("queue depth 3".to_string());
+        assert!(events.contains(&(Stage::MissedTest, None)));
+        assert!(events.contains(&(Stage::AtRisk, snapshot.clone())));
+        assert!(events.contains(&(Stage::Stalled, snapshot)));
+
+        // Every snapshot was taken on the same worker thread.
+        let threads: Vec<_> = threads_rx.try_iter().collect();
+        assert!(threads.len() >= 2);
+        assert!(threads.iter().all(|&id| id == threads[0]));
+    }
+
+    #[test]
+    fn hung_snapshot_times_out() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (vigil, thread) = Builder::new(100)
+            .arming(Arming::ArmedImmediately)
+            .snapshot(Duration::from_millis(20), || {
+                std::thread::sleep(Duration::from_millis(1000));
+                "too late".to_string()
+            })
+            .on_event(move |event| {
+                let _ = tx.send(event.snapshot.clone());
+            })
+            .build();
+        std::thread::sleep(Duration::from_millis(450));
+        drop(vigil);
+        thread.join().unwrap();
+        assert!(rx.try_iter().all(|snapshot| snapshot.is_none()));
+    }
+
+    #[test]
+    fn panicking_snapshot_recovers() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let calls = Arc::new(atomic::AtomicUsize::new(0));
+        let (vigil, thread) = Builder::new(100)
+            .arming(Arming::ArmedImmediately)
+            .snapshot(Duration::from_millis(50), {
+                let calls = calls.clone();
+                move || {
+                    if calls.fetch_add(1, atomic::Ordering::Relaxed) == 0 {
+                        panic!("snapshot failed");
+                    }
+                    "recovered".to_string()
+                }
+            })
+            .on_event(move |event| {
+                let _ = tx.send(event.snapshot.clone());
+            })
+            .build();
+        std::thread::sleep(Duration::from_millis(550));
+        drop(vigil);
+        thread.join().unwrap();
+        assert!(calls.load(atomic::Ordering::Relaxed) >= 2);
+        assert!(rx
+            .try_iter()
+            .any(|snapshot| snapshot.as_deref() == Some("recovered")));
+    }
+
+    #[test]
+    fn events_carry_severity() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (vigil, thread) = Builder::new(100)
+            .severities(Severities::default().with(Stage::AtRisk, Severity::Fatal))
+            .on_event({
+                let events = events.clone();
+                move |event| events.lock().unwrap().push((event.stage, event.severity))
+            })
+            .build();
+        vigil.notify();
+        std::thread::sleep(Duration::from_millis(450));
+        drop(vigil);
+        thread.join().unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(events.contains(&(Stage::MissedTest, Severity::Warn)));
+        assert!(events.contains(&(Stage::AtRisk, Severity::Fatal)));
+        assert!(events.contains(&(Stage::Stalled, Severity::Fatal)));
+    }
 }
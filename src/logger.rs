@@ -0,0 +1,138 @@
+//! Logging integrations which treat log output as a sign of life.
+//!
+//! Wrapping the application's logger in a `NotifyingLogger` means every log record emitted from a
+//! thread with a registered vigil (see `vigil::thread`) notifies that vigil, giving stall
+//! detection to chatty workers without adding explicit notifications:
+//!
+//! ```no_run
+//! struct StderrLogger;
+//!
+//! impl log::Log for StderrLogger {
+//!     fn enabled(&self, _: &log::Metadata) -> bool {
+//!         true
+//!     }
+//!
+//!     fn log(&self, record: &log::Record) {
+//!         eprintln!("{} {}", record.level(), record.args());
+//!     }
+//!
+//!     fn flush(&self) {}
+//! }
+//!
+//! static LOGGER: vigil::logger::NotifyingLogger<StderrLogger> =
+//!     vigil::logger::NotifyingLogger::new(StderrLogger);
+//!
+//! log::set_logger(&LOGGER).unwrap();
+//! log::set_max_level(log::LevelFilter::Info);
+//! ```
+//!
+//! With the `tracing` feature, `NotifyingLayer` does the same for `tracing` events.
+//!
+//! Note that a thread stuck in a loop that logs will appear live.
+use log::{Log, Metadata, Record};
+
+/// Logger which notifies the current thread's vigil on each record, then passes the record on to
+/// the wrapped logger.
+pub struct NotifyingLogger<L> {
+    inner: L,
+}
+
+impl<L> NotifyingLogger<L> {
+    /// Wrap `inner`.
+    pub const fn new(inner: L) -> Self {
+        NotifyingLogger { inner }
+    }
+
+    /// The wrapped logger.
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+}
+
+impl<L: Log> Log for NotifyingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        crate::thread::notify();
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// `tracing_subscriber` layer which notifies the current thread's vigil on each event.
+#[cfg(feature = "tracing")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NotifyingLayer;
+
+#[cfg(feature = "tracing")]
+impl<S: tracing_core::Subscriber> tracing_subscriber::Layer<S> for NotifyingLayer {
+    fn on_event(
+        &self,
+        _event: &tracing_core::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        crate::thread::notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, Stage};
+
+    struct NullLogger;
+
+    impl Log for NullLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            false
+        }
+
+        fn log(&self, _: &Record) {}
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn logging_notifies_registered_vigil() {
+        let (vigil, thread) = Builder::new(100).build();
+        crate::thread::register(vigil);
+        assert_eq!(
+            Some(Stage::Init),
+            crate::thread::with_current(|vigil| vigil.map(crate::Vigil::stage))
+        );
+
+        let logger = NotifyingLogger::new(NullLogger);
+        logger.log(&Record::builder().args(format_args!("working")).build());
+        assert_eq!(
+            Some(Stage::Live),
+            crate::thread::with_current(|vigil| vigil.map(crate::Vigil::stage))
+        );
+
+        drop(crate::thread::unregister());
+        thread.join().unwrap();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_event_notifies_registered_vigil() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (vigil, thread) = Builder::new(100).build();
+        crate::thread::register(vigil);
+
+        let subscriber = tracing_subscriber::registry().with(NotifyingLayer);
+        tracing::subscriber::with_default(subscriber, || tracing::info!("working"));
+        assert_eq!(
+            Some(Stage::Live),
+            crate::thread::with_current(|vigil| vigil.map(crate::Vigil::stage))
+        );
+
+        drop(crate::thread::unregister());
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,337 @@
+//! A registry of vigils, for health endpoints and tools which report on many vigils at once.
+//!
+//! Vigils are added with `Builder::registry` (optionally labelled with `Builder::label`) and are
+//! removed when dropped.  Queries filter on labels, stage and staleness and return results a page
+//! at a time, so a caller never needs to materialize the whole registry:
+//!
+//! ```
+//! use vigil::registry::{Query, Registry};
+//! use vigil::Stage;
+//!
+//! let registry = Registry::new();
+//! let (_vigil, _thread) = vigil::Builder::new(1000)
+//!     .registry(&registry)
+//!     .label("pool".to_string(), "io".to_string())
+//!     .build();
+//!
+//! let mut query = Query::new(100)
+//!     .label("pool".to_string(), "io".to_string())
+//!     .stages(&[Stage::AtRisk, Stage::Stalled]);
+//! loop {
+//!     let page = registry.query(&query);
+//!     for status in &page.statuses {
+//!         println!("{:?} is {:?}", status.name, status.stage);
+//!     }
+//!     match page.next {
+//!         Some(next) => query = query.after(next),
+//!         None => break,
+//!     }
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::sync::atomic;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::{Stage, VigilShared};
+
+/// The most vigils gathered under the registry lock at once by `Registry::for_each`.
+const CHUNK_SIZE: usize = 256;
+
+/// The process-wide default registry.  Threads spawned by `vigil::thread::Builder` are added to
+/// this unless configured otherwise.
+pub fn global() -> &'static Registry {
+    static GLOBAL: OnceLock<Registry> = OnceLock::new();
+    GLOBAL.get_or_init(Registry::new)
+}
+
+/// Identifies a vigil within a registry.  IDs are allocated in increasing order and never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VigilId(u64);
+
+/// A collection of vigils which can be queried.  Cloning a registry gives another handle to the
+/// same collection.
+#[derive(Clone, Default)]
+pub struct Registry {
+    inner: Arc<RegistryInner>,
+}
+
+#[derive(Default)]
+struct RegistryInner {
+    next_id: atomic::AtomicU64,
+    entries: Mutex<BTreeMap<VigilId, Entry>>,
+}
+
+struct Entry {
+    shared: Arc<VigilShared>,
+    labels: Vec<(String, String)>,
+}
+
+impl Registry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// The number of vigils in the registry.
+    pub fn len(&self) -> usize {
+        self.inner.entries.lock().unwrap().len()
+    }
+
+    /// Whether the registry has no vigils.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return one page of vigils matching `query`, in ID order.
+    pub fn query(&self, query: &Query) -> Page {
+        let mut statuses = Vec::new();
+        let mut next = None;
+        self.for_each_from(query, query.limit.saturating_add(1), |status| {
+            if statuses.len() == query.limit {
+                next = statuses.last().map(|status: &Status| status.id);
+                return false;
+            }
+            statuses.push(status);
+            true
+        });
+        Page { statuses, next }
+    }
+
+    /// Call `f` with every vigil matching `query` (ignoring its limit), streaming through the
+    /// registry rather than collecting the results.  The registry is only locked briefly to
+    /// gather each chunk of vigils, never while `f` runs, so `f` may be slow and may itself
+    /// create or drop vigils.
+    pub fn for_each<F: FnMut(Status)>(&self, query: &Query, mut f: F) {
+        self.for_each_from(query, CHUNK_SIZE, |status| {
+            f(status);
+            true
+        });
+    }
+
+    /// Call `f` with each matching vigil until it returns `false`, taking the lock for at most
+    /// `chunk_size` vigils at a time.
+    fn for_each_from<F: FnMut(Status) -> bool>(&self, query: &Query, chunk_size: usize, mut f: F) {
+        let mut after = query.after;
+        loop {
+            let chunk: Vec<_> = {
+                let entries = self.inner.entries.lock().unwrap();
+                let range = match after {
+                    Some(VigilId(after)) => entries.range(VigilId(after + 1)..),
+                    None => entries.range(..),
+                };
+                range
+                    .filter(|(_, entry)| {
+                        query
+                            .labels
+                            .iter()
+                            .all(|label| entry.labels.contains(label))
+                    })
+                    .take(chunk_size)
+                    .map(|(&id, entry)| (id, entry.shared.clone(), entry.labels.clone()))
+                    .collect()
+            };
+            let last_chunk = chunk.len() < chunk_size;
+
+            for (id, shared, labels) in chunk {
+                after = Some(id);
+                let stage = shared.stage();
+                if !query.stages.is_empty() && !query.stages.contains(&stage) {
+                    continue;
+                }
+                let since_notified = shared.since_notified();
+                if let Some(stale_for) = query.stale_for {
+                    if since_notified.is_some_and(|since| since < stale_for) {
+                        continue;
+                    }
+                }
+                let status = Status {
+                    id,
+                    name: shared.name.clone(),
+                    labels,
+                    stage,
+                    since_notified,
+                };
+                if !f(status) {
+                    return;
+                }
+            }
+
+            if last_chunk {
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn register(
+        &self,
+        shared: Arc<VigilShared>,
+        labels: Vec<(String, String)>,
+    ) -> Registration {
+        let id = VigilId(self.inner.next_id.fetch_add(1, atomic::Ordering::Relaxed));
+        self.inner
+            .entries
+            .lock()
+            .unwrap()
+            .insert(id, Entry { shared, labels });
+        Registration {
+            registry: self.clone(),
+            id,
+        }
+    }
+}
+
+/// A vigil's membership of a registry, removed when the vigil is dropped.
+pub(crate) struct Registration {
+    registry: Registry,
+    id: VigilId,
+}
+
+impl Registration {
+    pub(crate) fn unregister(&self) {
+        self.registry.inner.entries.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Which vigils to return from `Registry::query`.  All filters must match for a vigil to be
+/// included.
+#[derive(Clone, Debug)]
+pub struct Query {
+    labels: Vec<(String, String)>,
+    stages: Vec<Stage>,
+    stale_for: Option<Duration>,
+    after: Option<VigilId>,
+    limit: usize,
+}
+
+impl Query {
+    /// Match all vigils, returning at most `limit` (and at least one) per page.
+    pub fn new(limit: usize) -> Self {
+        Query {
+            labels: Vec::new(),
+            stages: Vec::new(),
+            stale_for: None,
+            after: None,
+            limit: limit.max(1),
+        }
+    }
+
+    /// Only match vigils with the given label.
+    pub fn label(mut self, key: String, value: String) -> Self {
+        self.labels.push((key, value));
+        self
+    }
+
+    /// Only match vigils in one of `stages`.
+    pub fn stages(mut self, stages: &[Stage]) -> Self {
+        self.stages = stages.to_vec();
+        self
+    }
+
+    /// Only match vigils which have not been notified for at least `stale_for` (including those
+    /// never notified).
+    pub fn stale_for(mut self, stale_for: Duration) -> Self {
+        self.stale_for = Some(stale_for);
+        self
+    }
+
+    /// Start after the given vigil, typically `Page::next` from the previous page.
+    pub fn after(mut self, after: VigilId) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
+
+/// A page of results from `Registry::query`.
+#[derive(Clone, Debug)]
+pub struct Page {
+    /// The matching vigils.
+    pub statuses: Vec<Status>,
+    /// Pass to `Query::after` to get the next page, or `None` if this is the last page.
+    pub next: Option<VigilId>,
+}
+
+/// The state of a single vigil in the registry.
+#[derive(Clone, Debug)]
+pub struct Status {
+    /// The vigil's ID within the registry.
+    pub id: VigilId,
+    /// The vigil's name (see `Builder::name`).
+    pub name: Option<String>,
+    /// The vigil's labels (see `Builder::label`).
+    pub labels: Vec<(String, String)>,
+    /// As `Vigil::stage`.
+    pub stage: Stage,
+    /// As `Vigil::since_notified`.
+    pub since_notified: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn paginated_filtered_query() {
+        let registry = Registry::new();
+        let vigils: Vec<_> = (0..5)
+            .map(|i| {
+                Builder::new(100)
+                    .name(format!("worker-{}", i))
+                    .registry(&registry)
+                    .label("parity".to_string(), (i % 2).to_string())
+                    .build()
+            })
+            .collect();
+        vigils[0].0.notify();
+        vigils[4].0.notify();
+        assert_eq!(5, registry.len());
+
+        let query = Query::new(2).label("parity".to_string(), "0".to_string());
+        let first = registry.query(&query);
+        let names: Vec<_> = first.statuses.iter().map(|s| s.name.clone()).collect();
+        assert_eq!(
+            vec![Some("worker-0".to_string()), Some("worker-2".to_string())],
+            names
+        );
+        let second = registry.query(&query.clone().after(first.next.unwrap()));
+        assert_eq!(1, second.statuses.len());
+        assert_eq!(Some("worker-4".to_string()), second.statuses[0].name);
+        assert_eq!(None, second.next);
+
+        let live = registry.query(&Query::new(10).stages(&[Stage::Live]));
+        assert_eq!(2, live.statuses.len());
+
+        let mut stale = 0;
+        registry.for_each(&Query::new(0).stale_for(Duration::from_secs(60)), |_| {
+            stale += 1
+        });
+        assert_eq!(3, stale);
+
+        for (vigil, thread) in vigils {
+            drop(vigil);
+            thread.join().unwrap();
+        }
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn callback_runs_outside_lock() {
+        let registry = Registry::new();
+        let mut vigils: Vec<_> = (0..5)
+            .map(|_| Builder::new(100).registry(&registry).build())
+            .collect();
+
+        let mut seen = 0;
+        registry.for_each_from(&Query::new(1), 2, |_| {
+            seen += 1;
+            let (vigil, thread) = vigils.pop().unwrap();
+            drop(vigil);
+            thread.join().unwrap();
+            true
+        });
+        // Vigils 4 and 3 are dropped while visiting 0 and 1, then 2 while visiting itself.
+        assert_eq!(3, seen);
+        assert_eq!(2, registry.len());
+    }
+}
@@ -0,0 +1,178 @@
+//! Wrappers around `std::thread` which place each spawned thread under its own vigil.
+//!
+//! Threads spawned through `Builder` get a vigil named after the thread, which is added to the
+//! default registry (`vigil::registry::global`) and registered as the thread's current vigil.  Code running on the thread can then use `notify` and
+//! `set_interval` without needing to be handed the vigil explicitly:
+//!
+//! ```
+//! let worker = vigil::thread::Builder::new(vigil::Builder::new(1000))
+//!     .name("worker".to_string())
+//!     .spawn(|| {
+//!         for _ in 0..10 {
+//!             // do_work();
+//!             vigil::thread::notify();
+//!         }
+//!     })
+//!     .unwrap();
+//! worker.join().unwrap();
+//! ```
+//!
+//! The vigil is dropped (and so its watcher thread terminates) when the spawned thread exits.
+use std::cell::RefCell;
+use std::io;
+use std::thread;
+
+use crate::Vigil;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Vigil>> = const { RefCell::new(None) };
+}
+
+/// Equivalent of `std::thread::Builder` which places the spawned thread under a vigil.
+pub struct Builder {
+    thread: thread::Builder,
+    vigil: crate::Builder,
+}
+
+impl Builder {
+    /// Create a thread builder whose threads will be watched by a vigil configured by `vigil`.
+    /// Unless `vigil` was given a registry, the vigil is added to `vigil::registry::global`.
+    pub fn new(mut vigil: crate::Builder) -> Self {
+        if vigil.registry.is_none() {
+            vigil.registry = Some(crate::registry::global().clone());
+        }
+        Builder {
+            thread: thread::Builder::new(),
+            vigil,
+        }
+    }
+
+    /// Don't add the thread's vigil to any registry.
+    pub fn unregistered(mut self) -> Self {
+        self.vigil.registry = None;
+        self
+    }
+
+    /// Name the thread.  Unless the vigil has been named explicitly, it takes the same name.
+    pub fn name(mut self, name: String) -> Self {
+        if self.vigil.name.is_none() {
+            self.vigil.name = Some(name.clone());
+        }
+        self.thread = self.thread.name(name);
+        self
+    }
+
+    /// As `std::thread::Builder::stack_size`.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.thread = self.thread.stack_size(size);
+        self
+    }
+
+    /// Spawn the thread, registering the vigil as its current vigil before running `f`.  The
+    /// vigil's watcher thread is detached, and exits shortly after the spawned thread does.
+    pub fn spawn<F, T>(self, f: F) -> io::Result<thread::JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (vigil, _watcher) = self.vigil.try_build()?;
+        self.thread.spawn(move || {
+            register(vigil);
+            f()
+        })
+    }
+}
+
+/// Register `vigil` as the current thread's vigil, returning the previously registered vigil (if
+/// any).  The vigil is bound to the current thread (see `Vigil::bind_current_thread`) and will be
+/// dropped when the thread exits.
+pub fn register(vigil: Vigil) -> Option<Vigil> {
+    vigil.bind_current_thread();
+    CURRENT.with(|current| current.borrow_mut().replace(vigil))
+}
+
+/// Remove the current thread's vigil, returning it (if any).
+pub fn unregister() -> Option<Vigil> {
+    CURRENT.with(|current| current.borrow_mut().take())
+}
+
+/// Run `f` with the current thread's vigil (if any).
+pub fn with_current<F: FnOnce(Option<&Vigil>) -> R, R>(f: F) -> R {
+    CURRENT.with(|current| f(current.borrow().as_ref()))
+}
+
+/// Notify the current thread's vigil.  Does nothing if the thread has no vigil (including while
+/// the thread is exiting, so this is safe to call from destructors and loggers).
+pub fn notify() {
+    let _ = CURRENT.try_with(|current| {
+        if let Some(ref vigil) = *current.borrow() {
+            vigil.notify();
+        }
+    });
+}
+
+/// Change the interval of the current thread's vigil.  Like `notify`, does nothing if the thread
+/// has no vigil (including while the thread is exiting).
+pub fn set_interval(interval_ms: usize) {
+    let _ = CURRENT.try_with(|current| {
+        if let Some(ref vigil) = *current.borrow() {
+            vigil.set_interval(interval_ms);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stage;
+
+    #[test]
+    fn spawned_thread_has_named_vigil() {
+        let handle = Builder::new(crate::Builder::new(100))
+            .name("worker".to_string())
+            .spawn(|| {
+                notify();
+                with_current(|vigil| {
+                    let vigil = vigil.unwrap();
+                    let thread = vigil.thread().unwrap();
+                    assert_eq!(Some("worker"), thread.name.as_deref());
+                    (vigil.name().map(str::to_string), vigil.stage())
+                })
+            })
+            .unwrap();
+        assert_eq!(
+            (Some("worker".to_string()), Stage::Live),
+            handle.join().unwrap()
+        );
+    }
+
+    #[test]
+    fn spawned_thread_is_in_global_registry() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let handle = Builder::new(crate::Builder::new(100))
+            .name("registered-worker".to_string())
+            .spawn(move || {
+                tx.send(()).unwrap();
+                let _ = done_rx.recv();
+            })
+            .unwrap();
+        rx.recv().unwrap();
+
+        let mut names = Vec::new();
+        crate::registry::global().for_each(&crate::registry::Query::new(1), |status| {
+            names.push(status.name)
+        });
+        assert!(names.contains(&Some("registered-worker".to_string())));
+
+        drop(done_tx);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn unregistered_thread_is_noop() {
+        notify();
+        set_interval(100);
+        assert!(with_current(|vigil| vigil.is_none()));
+    }
+}
@@ -0,0 +1,110 @@
+//! Async helpers which manage a vigil's interval while awaiting, available with the `async`
+//! feature.  These are the async equivalent of `Vigil::extend`: the vigil's interval is extended
+//! to cover the timeout, and `phase` recorded in its logs and events, while the future is
+//! pending.  Both are undone once it completes, times out or is cancelled.
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::time::error::Elapsed;
+
+use crate::Vigil;
+
+/// Await `future` for at most `duration`, extending `vigil`'s interval to `duration` and
+/// recording `phase` while doing so.
+pub async fn timeout<F: Future>(
+    vigil: &Vigil,
+    phase: &'static str,
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    let _guard = vigil.extend(interval_ms(duration), phase);
+    tokio::time::timeout(duration, future).await
+}
+
+/// Convert `duration` to an interval, rounding up so that sub-millisecond timeouts don't produce
+/// a zero interval (which would have the watcher spin).
+fn interval_ms(duration: Duration) -> usize {
+    usize::try_from(duration.as_nanos().div_ceil(1_000_000).max(1)).unwrap_or(usize::MAX)
+}
+
+/// Lock `mutex`, waiting for at most `duration`, extending `vigil`'s interval to `duration` and
+/// recording `phase` while doing so.
+pub async fn lock<'a, T>(
+    vigil: &Vigil,
+    phase: &'static str,
+    duration: Duration,
+    mutex: &'a Mutex<T>,
+) -> Result<MutexGuard<'a, T>, Elapsed> {
+    timeout(vigil, phase, duration, mutex.lock()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stage;
+    use std::sync::atomic;
+
+    #[tokio::test]
+    async fn timeout_extends_interval() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        let interval = timeout(&vigil, "sleeping", Duration::from_millis(500), async {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            vigil.shared.tick_interval.load(atomic::Ordering::Relaxed)
+        })
+        .await
+        .unwrap();
+        assert_eq!(500, interval);
+        assert_eq!(
+            100,
+            vigil.shared.tick_interval.load(atomic::Ordering::Relaxed)
+        );
+        assert_eq!(Stage::Live, vigil.stage());
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn interval_rounds_up_and_clamps() {
+        assert_eq!(1, interval_ms(Duration::ZERO));
+        assert_eq!(1, interval_ms(Duration::from_micros(10)));
+        assert_eq!(2, interval_ms(Duration::from_micros(1001)));
+        assert_eq!(usize::MAX, interval_ms(Duration::MAX));
+    }
+
+    #[tokio::test]
+    async fn lock_times_out() {
+        let (vigil, thread) = Vigil::create(100, None, None, None);
+        let mutex = Mutex::new(());
+        let held = mutex.lock().await;
+        assert!(lock(&vigil, "locking", Duration::from_millis(50), &mutex)
+            .await
+            .is_err());
+        drop(held);
+        assert!(lock(&vigil, "locking", Duration::from_millis(50), &mutex)
+            .await
+            .is_ok());
+        drop(vigil);
+        thread.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn pending_lock_records_phase() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (vigil, thread) = crate::Builder::new(20)
+            .on_event(move |event| {
+                let _ = tx.send(event.phases.clone());
+            })
+            .build();
+        let mutex = Mutex::new(());
+        let held = mutex.lock().await;
+        assert!(lock(&vigil, "db lock", Duration::from_millis(200), &mutex)
+            .await
+            .is_err());
+        assert!(vigil.shared.phases().is_empty());
+        drop(held);
+        drop(vigil);
+        thread.join().unwrap();
+        assert!(rx.try_iter().any(|phases| phases == ["db lock"]));
+    }
+}